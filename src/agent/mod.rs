@@ -0,0 +1,55 @@
+//! Loads a command agent's `.devai` file from disk given a `cmd_agent` name/path.
+
+use crate::run::{DirContext, PathResolver};
+use crate::{Error, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// A loaded agent script: where it came from and its current file content.
+#[derive(Debug, Clone)]
+pub struct Agent {
+	path: PathBuf,
+	content: String,
+}
+
+impl Agent {
+	/// Stable identifier for this agent, used to key per-agent cache indexes and run-result
+	/// stores. Derived from the resolved path rather than the raw `cmd_agent` string so that
+	/// `./foo.devai` and `foo.devai` key the same agent.
+	pub fn key(&self) -> String {
+		// `components()` drops interior `.` segments, so `base/./foo.devai` keys as `base/foo.devai`.
+		let normalized: PathBuf = self.path.components().collect();
+		normalized.to_string_lossy().replace(['/', '\\'], "_")
+	}
+
+	/// Hash of the agent's current file content, used to invalidate the cache when the
+	/// agent script itself changes even if none of its declared dependencies did.
+	pub fn content_hash(&self) -> String {
+		let mut hasher = DefaultHasher::new();
+		self.content.hash(&mut hasher);
+		format!("{:x}", hasher.finish())
+	}
+
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	pub fn content(&self) -> &str {
+		&self.content
+	}
+}
+
+/// Resolves `cmd_agent` (a path given by a script or the CLI) against `dir_context` per
+/// `resolver`, then loads it into an [`Agent`].
+pub fn find_agent(cmd_agent: &str, dir_context: &DirContext, resolver: PathResolver) -> Result<Agent> {
+	let base = match resolver {
+		PathResolver::DevaiParentDir => Path::new(dir_context.devai_parent_dir().as_str()),
+	};
+	let path = base.join(cmd_agent);
+	if !path.is_file() {
+		return Err(Error::cc("agent not found", format!("no such agent file: {}", path.display())));
+	}
+	let content = std::fs::read_to_string(&path)?;
+	Ok(Agent { path, content })
+}
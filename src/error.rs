@@ -0,0 +1,82 @@
+//! The crate's single error type and `Result` alias.
+//!
+//! Most call sites build an error with context via [`Error::cc`] ("context + cause");
+//! the dedicated variants exist where a caller needs to match on *what* went wrong rather
+//! than just read the message (e.g. the CLI printing an actionable location for a script
+//! error).
+
+use std::fmt;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+	/// Generic "context: cause" error, used when callers only need a message, not a variant
+	/// to match on.
+	Custom(String),
+
+	/// `devai::run`/`devai::run_with` require a Tokio runtime handle to already be current.
+	TokioTryCurrent(tokio::runtime::TryCurrentError),
+
+	/// A `let`/`const` in an agent script shadows a runtime-injected name (`input`,
+	/// `before_all`, `options`, `devai`).
+	ReservedVarShadowed { name: String, position: rhai::Position },
+
+	/// An agent script defines the same named function twice.
+	DuplicateFunctionDef { name: String },
+}
+
+impl Error {
+	/// Build a `Custom` error from a short context message plus anything `Display`-able as
+	/// the underlying cause (another error, a string, ...).
+	pub fn cc(context: impl fmt::Display, cause: impl fmt::Display) -> Self {
+		Self::Custom(format!("{context}\n    cause: {cause}"))
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Error::Custom(msg) => write!(f, "{msg}"),
+			Error::TokioTryCurrent(err) => write!(f, "no current tokio runtime: {err}"),
+			Error::ReservedVarShadowed { name, position } => {
+				write!(f, "cannot shadow reserved variable '{name}' at {position}")
+			}
+			Error::DuplicateFunctionDef { name } => {
+				write!(f, "duplicate definition of function '{name}' in agent script")
+			}
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+	fn from(err: std::io::Error) -> Self {
+		Error::cc("io error", err)
+	}
+}
+
+impl From<&str> for Error {
+	fn from(msg: &str) -> Self {
+		Error::Custom(msg.to_string())
+	}
+}
+
+impl From<String> for Error {
+	fn from(msg: String) -> Self {
+		Error::Custom(msg)
+	}
+}
+
+/// Lets `return Err(Error::ReservedVarShadowed { .. }.into())` type-check at a Rhai call
+/// site that expects `Box<EvalAltResult>` (e.g. inside `Engine::on_def_var`).
+impl From<Error> for Box<rhai::EvalAltResult> {
+	fn from(err: Error) -> Self {
+		let position = match &err {
+			Error::ReservedVarShadowed { position, .. } => *position,
+			_ => rhai::Position::NONE,
+		};
+		Box::new(rhai::EvalAltResult::ErrorRuntime(err.to_string().into(), position))
+	}
+}
@@ -0,0 +1,21 @@
+//! Events `run_command_agent` dispatches as a run progresses. Consumed by `devai::run_with`'s
+//! `on_event` callback (see `rhai_modules::rhai_devai::run_with_inputs_and_events`), and by
+//! `devai::run`'s `noop_on_event`.
+
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "data")]
+pub enum ExecEvent {
+	/// An input's `# Data`/AI/`# Output` flow is about to start.
+	InputStarted { index: usize },
+	/// An input was skipped because its cached output was fresh; no `InputDone` follows.
+	InputSkipped { index: usize, reason: Option<String> },
+	/// An input ran to completion with `output`.
+	InputDone { index: usize, output: Option<Value> },
+	/// `# After All` finished; `after_all` is whatever the per-run state ended up holding.
+	AfterAllDone { after_all: Option<Value> },
+	/// An input failed; the run continues with the remaining inputs.
+	Error { index: Option<usize>, message: String },
+}
@@ -0,0 +1,30 @@
+//! The callback shape `run_command_agent` (the executor) dispatches `ExecEvent`s through.
+//! Kept as its own small type rather than inlined so both `devai::run` (which passes a
+//! no-op) and `devai::run_with` (which passes a real forwarding closure) share one name for
+//! it instead of each spelling out the `&dyn Fn(ExecEvent)` shape independently.
+
+use crate::exec::ExecEvent;
+
+pub type OnExecEvent<'a> = &'a dyn Fn(ExecEvent);
+
+/// Dispatches `event` through `on_event`. A thin wrapper rather than calling `on_event`
+/// directly is only worth it because `run_command_agent` has several call sites that need
+/// to build the same `Error` variant to go with an event -- see `emit_error`.
+pub fn emit(on_event: OnExecEvent<'_>, event: ExecEvent) {
+	on_event(event);
+}
+
+/// Reports a single input's failure without aborting the rest of the run: emits
+/// `ExecEvent::Error` for `index` and returns the message, so the caller can fold it
+/// straight into the `InputStatus::Errored` bookkeeping it already has to do.
+pub fn emit_error(on_event: OnExecEvent<'_>, index: usize, err: &crate::Error) -> String {
+	let message = err.to_string();
+	emit(
+		on_event,
+		ExecEvent::Error {
+			index: Some(index),
+			message: message.clone(),
+		},
+	);
+	message
+}
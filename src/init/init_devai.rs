@@ -46,6 +46,9 @@ fn create_or_refresh_devai_files(devai_parent_dir: &SPath) -> Result<()> {
 		ensure_dir(dir)?;
 	}
 
+	// -- Create the lib dir (root for shared/importable Rhai modules)
+	ensure_dir(devai_dir.get_lib_dir()?)?;
+
 	// -- migrate_devai_0_1_0_if_needed
 	migrate_devai_0_1_0_if_needed(devai_dir)?;
 
@@ -0,0 +1,16 @@
+// region:    --- Modules
+
+mod error;
+
+pub mod agent;
+pub mod exec;
+pub mod run;
+pub mod script;
+
+// Note: `init` (devai-dir scaffolding/embedded templates) predates this series and is not
+// wired into the crate root yet -- it depends on a `support` module and `_base/` embedded
+// assets that don't exist in this tree. Left un-declared here rather than pulled in half-built.
+
+pub use error::{Error, Result};
+
+// endregion: --- Modules
@@ -0,0 +1,13 @@
+//! Handle to the AI backend `run_command_agent` calls into to turn a rendered instruction
+//! into model output. This slice of the crate doesn't carry a model client yet, so this is
+//! a cheap-to-clone placeholder `run_command_agent` threads through -- the seam a real
+//! client would be added behind, rather than something each caller constructs itself.
+
+#[derive(Debug, Clone, Default)]
+pub struct Runtime;
+
+impl Runtime {
+	pub fn new() -> Self {
+		Self
+	}
+}
@@ -0,0 +1,270 @@
+//! Input-level incremental caching backing `devai::depends_on` / `devai::depends_on_env` /
+//! `devai::cache_skip_if_fresh`.
+//!
+//! For each input, a fingerprint is computed from the input JSON, the size and modification
+//! time of every declared dependency path, every declared env value, and the agent file's
+//! own hash. Entries are kept in a small JSON index (one per agent) under the devai dir,
+//! keyed by a hash of the input JSON; each entry holds the fingerprint it was stored under,
+//! and is only reused while the current fingerprint still matches it. A missing dependency
+//! file, or an agent source change, always changes the fingerprint and so always invalidates.
+
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Dependencies declared (via `devai::depends_on` / `devai::depends_on_env`) for the input
+/// currently being processed. `run_command_agent` creates a new one for each input.
+#[derive(Debug, Clone, Default)]
+pub struct CacheDeps {
+	inner: Arc<Mutex<CacheDepsInner>>,
+}
+
+#[derive(Debug, Default)]
+struct CacheDepsInner {
+	paths: Vec<PathBuf>,
+	env_names: Vec<String>,
+}
+
+impl CacheDeps {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn depends_on(&self, path: impl Into<PathBuf>) -> Result<()> {
+		let mut guard = self.inner.lock().map_err(|_| Error::cc("CacheDeps poisoned", "depends_on"))?;
+		guard.paths.push(path.into());
+		Ok(())
+	}
+
+	pub fn depends_on_env(&self, name: impl Into<String>) -> Result<()> {
+		let mut guard = self
+			.inner
+			.lock()
+			.map_err(|_| Error::cc("CacheDeps poisoned", "depends_on_env"))?;
+		guard.env_names.push(name.into());
+		Ok(())
+	}
+
+	/// Clears the declared dependencies.
+	pub fn reset(&self) -> Result<()> {
+		let mut guard = self.inner.lock().map_err(|_| Error::cc("CacheDeps poisoned", "reset"))?;
+		guard.paths.clear();
+		guard.env_names.clear();
+		Ok(())
+	}
+
+	fn snapshot(&self) -> Result<(Vec<PathBuf>, Vec<String>)> {
+		let guard = self.inner.lock().map_err(|_| Error::cc("CacheDeps poisoned", "snapshot"))?;
+		Ok((guard.paths.clone(), guard.env_names.clone()))
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+	fingerprint: String,
+	output: Value,
+}
+
+/// `{input key -> (fingerprint, last_output)}`, persisted as one JSON file per agent under the
+/// devai dir.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheIndex {
+	entries: BTreeMap<String, CacheEntry>,
+}
+
+impl CacheIndex {
+	fn load(path: &Path) -> Result<Self> {
+		if !path.exists() {
+			return Ok(Self::default());
+		}
+		let content = fs::read_to_string(path).map_err(|err| Error::cc("cache index, failed to read", err))?;
+		serde_json::from_str(&content).map_err(|err| Error::cc("cache index, failed to parse", err))
+	}
+
+	fn save(&self, path: &Path) -> Result<()> {
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent).map_err(|err| Error::cc("cache index, failed to create dir", err))?;
+		}
+		let content = serde_json::to_string_pretty(self).map_err(|err| Error::cc("cache index, failed to serialize", err))?;
+		fs::write(path, content).map_err(|err| Error::cc("cache index, failed to write", err))
+	}
+}
+
+/// Computes the fingerprint for one input. Returns `Err` (never a silently-stable
+/// fingerprint) when a declared dependency path does not exist, since a missing
+/// dependency must always invalidate the cache.
+fn compute_fingerprint(input: &Value, dep_paths: &[PathBuf], env_names: &[String], agent_hash: &str) -> Result<String> {
+	let mut hasher = std::collections::hash_map::DefaultHasher::new();
+
+	input.to_string().hash(&mut hasher);
+	agent_hash.hash(&mut hasher);
+
+	for path in dep_paths {
+		let metadata = fs::metadata(path).map_err(|_| {
+			Error::cc(
+				"devai cache, declared dependency is missing, invalidating cache",
+				path.display().to_string(),
+			)
+		})?;
+		path.to_string_lossy().hash(&mut hasher);
+		metadata.len().hash(&mut hasher);
+		if let Ok(modified) = metadata.modified() {
+			modified.hash(&mut hasher);
+		}
+	}
+
+	for name in env_names {
+		name.hash(&mut hasher);
+		std::env::var(name).unwrap_or_default().hash(&mut hasher);
+	}
+
+	Ok(format!("{:016x}", hasher.finish()))
+}
+
+/// One agent's cache index, bound to its on-disk JSON file and the hash of the agent's own
+/// source (so a changed agent invalidates every one of its cached entries at once).
+pub struct AgentCache {
+	index_path: PathBuf,
+	agent_hash: String,
+	index: Mutex<CacheIndex>,
+}
+
+impl AgentCache {
+	pub fn load(index_path: impl Into<PathBuf>, agent_hash: impl Into<String>) -> Result<Self> {
+		let index_path = index_path.into();
+		let index = CacheIndex::load(&index_path)?;
+		Ok(Self {
+			index_path,
+			agent_hash: agent_hash.into(),
+			index: Mutex::new(index),
+		})
+	}
+
+	/// Returns the cached output for `input` if its fingerprint (input json + deps + agent
+	/// hash) matches what is stored, `None` otherwise (not cached, stale, or a dependency is
+	/// missing).
+	pub fn fresh_output(&self, input: &Value, deps: &CacheDeps) -> Result<Option<Value>> {
+		let (paths, env_names) = deps.snapshot()?;
+		let fingerprint = match compute_fingerprint(input, &paths, &env_names, &self.agent_hash) {
+			Ok(fp) => fp,
+			// A missing dependency (or anything else that prevents fingerprinting) is never
+			// treated as "fresh" -- fall through to "not cached".
+			Err(_) => return Ok(None),
+		};
+
+		let guard = self
+			.index
+			.lock()
+			.map_err(|_| Error::cc("AgentCache poisoned", "fresh_output"))?;
+
+		let key = input_key(input);
+		Ok(guard
+			.entries
+			.get(&key)
+			.filter(|entry| entry.fingerprint == fingerprint)
+			.map(|entry| entry.output.clone()))
+	}
+
+	/// Record `output` for `input` under its current fingerprint and persist the index.
+	pub fn store(&self, input: &Value, deps: &CacheDeps, output: Value) -> Result<()> {
+		let (paths, env_names) = deps.snapshot()?;
+		let fingerprint = compute_fingerprint(input, &paths, &env_names, &self.agent_hash)?;
+
+		let mut guard = self.index.lock().map_err(|_| Error::cc("AgentCache poisoned", "store"))?;
+		guard.entries.insert(input_key(input), CacheEntry { fingerprint, output });
+		guard.save(&self.index_path)
+	}
+}
+
+/// The cache is keyed per distinct input value (not the fingerprint itself), so a changed
+/// input for the same position in the list still gets its own entry instead of colliding.
+fn input_key(input: &Value) -> String {
+	let mut hasher = std::collections::hash_map::DefaultHasher::new();
+	input.to_string().hash(&mut hasher);
+	format!("{:016x}", hasher.finish())
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	type Error = Box<dyn std::error::Error>;
+	type Result<T> = core::result::Result<T, Error>;
+
+	use super::*;
+	use serde_json::json;
+
+	fn tmp_index_path(name: &str) -> PathBuf {
+		std::env::temp_dir().join(format!("devai_test_cache_index_{name}.json"))
+	}
+
+	#[test]
+	fn test_cache_fresh_after_store_with_unchanged_deps() -> Result<()> {
+		let dep_path = std::env::temp_dir().join("devai_test_cache_dep_fresh.txt");
+		fs::write(&dep_path, "hello")?;
+
+		let deps = CacheDeps::new();
+		deps.depends_on(&dep_path)?;
+
+		let index_path = tmp_index_path("fresh");
+		let _ = fs::remove_file(&index_path);
+		let cache = AgentCache::load(&index_path, "agent-hash-v1")?;
+
+		let input = json!({"name": "a.rs"});
+		assert_eq!(cache.fresh_output(&input, &deps)?, None);
+
+		cache.store(&input, &deps, json!("output-for-a"))?;
+		assert_eq!(cache.fresh_output(&input, &deps)?, Some(json!("output-for-a")));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_cache_invalidates_when_dependency_file_missing() -> Result<()> {
+		let dep_path = std::env::temp_dir().join("devai_test_cache_dep_missing.txt");
+		fs::write(&dep_path, "hello")?;
+
+		let deps = CacheDeps::new();
+		deps.depends_on(&dep_path)?;
+
+		let index_path = tmp_index_path("missing_dep");
+		let _ = fs::remove_file(&index_path);
+		let cache = AgentCache::load(&index_path, "agent-hash-v1")?;
+
+		let input = json!({"name": "a.rs"});
+		cache.store(&input, &deps, json!("output-for-a"))?;
+		assert_eq!(cache.fresh_output(&input, &deps)?, Some(json!("output-for-a")));
+
+		// Delete the declared dependency: must invalidate, never silently stay "fresh".
+		fs::remove_file(&dep_path)?;
+		assert_eq!(cache.fresh_output(&input, &deps)?, None);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_cache_invalidates_when_agent_hash_changes() -> Result<()> {
+		let deps = CacheDeps::new();
+
+		let index_path = tmp_index_path("agent_hash");
+		let _ = fs::remove_file(&index_path);
+
+		let cache_v1 = AgentCache::load(&index_path, "agent-hash-v1")?;
+		let input = json!({"name": "a.rs"});
+		cache_v1.store(&input, &deps, json!("output-for-a"))?;
+		assert_eq!(cache_v1.fresh_output(&input, &deps)?, Some(json!("output-for-a")));
+
+		// Same on-disk index, but the agent source changed -> different agent_hash.
+		let cache_v2 = AgentCache::load(&index_path, "agent-hash-v2")?;
+		assert_eq!(cache_v2.fresh_output(&input, &deps)?, None);
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,117 @@
+//! Locates and lays out the `.devai` dir for the current project.
+
+use crate::Result;
+use simple_fs::SPath;
+use std::path::{Path, PathBuf};
+
+/// The project root (the directory a `.devai` dir was found in, or created under) plus a
+/// handle to that `.devai` dir.
+#[derive(Debug, Clone)]
+pub struct DirContext {
+	devai_parent_dir: SPath,
+	devai_dir: DevaiDir,
+}
+
+impl DirContext {
+	/// Walks up from the current working directory looking for an existing `.devai` dir.
+	/// Returns `None` (not an error) when none is found, so the caller can fall back to
+	/// creating one under the current directory.
+	pub fn load() -> Result<Option<Self>> {
+		let mut dir = std::env::current_dir()?;
+		loop {
+			if dir.join(".devai").is_dir() {
+				let parent = SPath::new(dir.to_string_lossy().to_string());
+				let devai_dir = DevaiDir::from_parent_dir(&parent)?;
+				return Ok(Some(Self {
+					devai_parent_dir: parent,
+					devai_dir,
+				}));
+			}
+			if !dir.pop() {
+				return Ok(None);
+			}
+		}
+	}
+
+	pub fn devai_parent_dir(&self) -> &SPath {
+		&self.devai_parent_dir
+	}
+
+	pub fn devai_dir(&self) -> &DevaiDir {
+		&self.devai_dir
+	}
+}
+
+/// Path layout of the `.devai` dir: default/custom command agents, new-command templates,
+/// config, doc, and the `lib/` dir for shared Rhai modules (see `rhai_engine`).
+#[derive(Debug, Clone)]
+pub struct DevaiDir {
+	root: PathBuf,
+}
+
+impl AsRef<Path> for DevaiDir {
+	fn as_ref(&self) -> &Path {
+		&self.root
+	}
+}
+
+impl DevaiDir {
+	pub fn from_parent_dir(parent: &SPath) -> Result<Self> {
+		let root = Path::new(parent.as_str()).join(".devai");
+		Ok(Self { root })
+	}
+
+	pub fn get_command_agent_default_dir(&self) -> Result<PathBuf> {
+		Ok(self.root.join("custom/command-agent/default"))
+	}
+
+	pub fn get_command_agent_custom_dir(&self) -> Result<PathBuf> {
+		Ok(self.root.join("custom/command-agent"))
+	}
+
+	pub fn get_new_template_command_dirs(&self) -> Result<Vec<PathBuf>> {
+		Ok(vec![
+			self.root.join("custom/new-template/command-agent"),
+			self.get_new_template_command_default_dir()?,
+		])
+	}
+
+	pub fn get_new_template_solo_dirs(&self) -> Result<Vec<PathBuf>> {
+		Ok(vec![
+			self.root.join("custom/new-template/solo-agent"),
+			self.get_new_template_solo_default_dir()?,
+		])
+	}
+
+	pub fn get_new_template_command_default_dir(&self) -> Result<PathBuf> {
+		Ok(self.root.join("custom/new-template/command-agent/default"))
+	}
+
+	pub fn get_new_template_solo_default_dir(&self) -> Result<PathBuf> {
+		Ok(self.root.join("custom/new-template/solo-agent/default"))
+	}
+
+	pub fn get_config_toml_path(&self) -> Result<PathBuf> {
+		Ok(self.root.join("config.toml"))
+	}
+
+	pub fn get_doc_dir(&self) -> Result<PathBuf> {
+		Ok(self.root.join("doc"))
+	}
+
+	pub fn get_doc_rhai_path(&self) -> Result<PathBuf> {
+		Ok(self.get_doc_dir()?.join("rhai.md"))
+	}
+
+	/// Root for shared/importable `.rhai`/`.devai` library modules (see
+	/// `rhai_engine::build_agent_engine`, which roots its `FileModuleResolver` here).
+	pub fn get_lib_dir(&self) -> Result<PathBuf> {
+		Ok(self.root.join("lib"))
+	}
+
+	/// Where per-agent cache indexes (see `crate::run::cache_index`) and run records (see
+	/// `crate::run::run_result_store`) are persisted.
+	pub fn get_store_dir(&self) -> Result<PathBuf> {
+		Ok(self.root.join("store"))
+	}
+}
@@ -0,0 +1,23 @@
+// region:    --- Modules
+
+mod ai_runtime;
+mod cache_index;
+mod dir_context;
+mod path_resolver;
+mod run_base_options;
+mod run_command_agent;
+mod run_result_store;
+mod run_state;
+mod runtime_context;
+
+pub use ai_runtime::*;
+pub use cache_index::*;
+pub use dir_context::*;
+pub use path_resolver::*;
+pub use run_base_options::*;
+pub use run_command_agent::*;
+pub use run_result_store::*;
+pub use run_state::*;
+pub use runtime_context::*;
+
+// endregion: --- Modules
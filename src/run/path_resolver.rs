@@ -0,0 +1,9 @@
+//! How `find_agent` should interpret a `cmd_agent` path given by a script or the CLI.
+
+/// Where to resolve a relative `cmd_agent` path from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathResolver {
+	/// Resolve relative to the devai dir's parent (the project root), which is how
+	/// `devai::run`/`devai::run_with` resolve the `cmd_agent` argument from a script.
+	DevaiParentDir,
+}
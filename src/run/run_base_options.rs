@@ -0,0 +1,18 @@
+//! Options threaded through every `run_command_agent` invocation.
+
+use crate::run::RunState;
+
+#[derive(Debug, Clone, Default)]
+pub struct RunBaseOptions {
+	/// When set on a nested run (`is_nested`), the new `RuntimeContext` built for this run
+	/// shares this `RunState` instead of creating a fresh one; a top-level run ignores it. Nothing in this crate sets this today -- a nested
+	/// `devai::run(...)`/`devai::run_with(...)` always passes `RunBaseOptions::default()`
+	/// (i.e. `None` here), which is what makes a nested run get its own fresh state by
+	/// default. This is the explicit, opt-in knob a caller would set to share state with a
+	/// sub-run instead.
+	pub inherit_state: Option<RunState>,
+
+	/// When set, only inputs that previously errored or were never reached (per the prior
+	/// `RunRecord`) are re-executed; everything else is fed through from the stored output.
+	pub resume: bool,
+}
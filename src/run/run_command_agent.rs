@@ -0,0 +1,181 @@
+//! The function every `devai::run`/`devai::run_with` call (see `rhai_modules::rhai_devai`)
+//! delegates to: builds this call's own [`RuntimeContext`] and runs the agent over its
+//! inputs.
+
+use crate::agent::Agent;
+use crate::exec::{emit, emit_error, ExecEvent, OnExecEvent};
+use crate::run::{
+	inputs_to_rerun, AgentCache, CacheDeps, DirContext, InputStatus, RunBaseOptions, RunRecord, RunResultStore,
+	RunState, Runtime, RuntimeContext,
+};
+use crate::script::rhai_script::{build_agent_engine, compile_agent_script};
+use crate::{Error, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// What a `devai::run`/`devai::run_with` call returns to the script: one output per input
+/// (`None` for an input that errored) plus whatever the per-run state held once `# After All` ran.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunOutput {
+	pub outputs: Vec<Option<Value>>,
+	pub after_all: Option<Value>,
+}
+
+/// Runs `agent` over `inputs` (a single `Value::Null` input when `inputs` is `None`).
+///
+/// Builds a fresh [`RuntimeContext`] for this call: a new [`RunState`] unless `is_nested` is
+/// true and `base_options.inherit_state` was set, in which case that state is shared instead
+/// -- so a `devai::run(...)` nested inside an already-running agent is isolated from its
+/// caller's state by default, and only shares it when the caller opts in via
+/// `RunBaseOptions::inherit_state`.
+pub async fn run_command_agent(
+	runtime: &Runtime,
+	agent: &Agent,
+	inputs: Option<Vec<Value>>,
+	base_options: &RunBaseOptions,
+	is_nested: bool,
+	on_event: OnExecEvent<'_>,
+) -> Result<RunOutput> {
+	let dir_context =
+		DirContext::load()?.ok_or_else(|| Error::cc("run_command_agent", "no .devai dir found from the current directory"))?;
+	let devai_dir = dir_context.devai_dir();
+
+	// Compiling the agent script through the guarded engine is what actually exercises the
+	// reserved-name/duplicate-fn guards (see `script::rhai_script::reserved_names`) on a real
+	// run, instead of only in their own unit tests. `import`s are resolved only when an AST is
+	// evaluated, which this function does not do.
+	let engine = build_agent_engine(devai_dir.get_lib_dir()?);
+	let _ast = compile_agent_script(&engine, agent.content())?;
+
+	let agent_cache = Arc::new(AgentCache::load(
+		devai_dir.get_store_dir()?.join(format!("{}.cache.json", agent.key())),
+		agent.content_hash(),
+	)?);
+
+	let state = if is_nested {
+		base_options.inherit_state.clone().unwrap_or_default()
+	} else {
+		RunState::new()
+	};
+	let run_result_store = RunResultStore::new(devai_dir.get_store_dir()?);
+	let ctx = RuntimeContext::new(dir_context, runtime.clone(), state, run_result_store);
+
+	let inputs = inputs.unwrap_or_else(|| vec![Value::Null]);
+
+	// With `resume`, inputs the prior run ran or skipped are fed through from its record.
+	let prior = if base_options.resume { ctx.run_result_store()?.load_last_run(&agent.key())? } else { None };
+	let rerun = prior.as_ref().map(|record| inputs_to_rerun(record, inputs.len()));
+
+	let mut outputs = Vec::with_capacity(inputs.len());
+	let mut input_statuses = Vec::with_capacity(inputs.len());
+
+	for (index, input) in inputs.into_iter().enumerate() {
+		if let (Some(prior), Some(rerun)) = (&prior, &rerun) {
+			if !rerun.contains(&index) {
+				outputs.push(prior.outputs.get(index).cloned().flatten());
+				input_statuses.push(prior.input_statuses[index].clone());
+				continue;
+			}
+		}
+
+		emit(on_event, ExecEvent::InputStarted { index });
+
+		match run_one_input(&ctx, &agent_cache, index, &input, on_event) {
+			Ok(InputOutcome::Skipped(output)) => {
+				outputs.push(Some(output));
+				input_statuses.push(InputStatus::Skipped);
+			}
+			Ok(InputOutcome::Ran(output)) => {
+				outputs.push(Some(output));
+				input_statuses.push(InputStatus::Ran);
+			}
+			// A single bad input (a cache file that can't be read, a dependency path
+			// that can't be stat'd, ...) must not take the rest of the run down with
+			// it: record it as errored -- so a future `--resume` retries just this
+			// input via `inputs_to_rerun` -- and keep going.
+			Err(err) => {
+				emit_error(on_event, index, &err);
+				outputs.push(None);
+				input_statuses.push(InputStatus::Errored);
+			}
+		}
+	}
+
+	ctx.clear_current_cache()?;
+
+	let after_all = ctx.state_all().ok().filter(|value| !value.is_null());
+	emit(
+		on_event,
+		ExecEvent::AfterAllDone {
+			after_all: after_all.clone(),
+		},
+	);
+
+	let (run_id, timestamp) = run_id_and_timestamp();
+	let mut record = RunRecord::new(run_id, timestamp);
+	record.outputs = outputs.clone();
+	record.after_all = after_all.clone();
+	record.input_statuses = input_statuses;
+	ctx.run_result_store()?.save(&agent.key(), &record)?;
+
+	Ok(RunOutput { outputs, after_all })
+}
+
+/// What running a single input produced, before it is folded into `run_command_agent`'s
+/// `outputs`/`input_statuses` bookkeeping.
+enum InputOutcome {
+	Skipped(Value),
+	Ran(Value),
+}
+
+/// Runs the cache-check -> (stubbed) AI call -> cache-store flow for one input, emitting the
+/// `InputSkipped`/`InputDone` events along the way. Pulled out of `run_command_agent`'s loop
+/// so that loop can catch a single input's error with `?` here instead of letting it abort
+/// every remaining input.
+fn run_one_input(
+	ctx: &RuntimeContext,
+	agent_cache: &Arc<AgentCache>,
+	index: usize,
+	input: &Value,
+	on_event: OnExecEvent<'_>,
+) -> Result<InputOutcome> {
+	let deps = CacheDeps::new();
+	ctx.set_current_cache(agent_cache.clone(), deps.clone(), input.clone())?;
+
+	if let Some(cached) = agent_cache.fresh_output(input, &deps)? {
+		emit(
+			on_event,
+			ExecEvent::InputSkipped {
+				index,
+				reason: Some("cache fresh, reusing stored output".to_string()),
+			},
+		);
+		return Ok(InputOutcome::Skipped(cached));
+	}
+
+	// TODO: the real instruction-render -> model-call -> output-render flow belongs here;
+	// this crate slice doesn't carry a model client yet (see `agent.content()` for what
+	// would be compiled and evaluated), so the output is the input echoed back.
+	let output = input.clone();
+	agent_cache.store(input, &deps, output.clone())?;
+
+	emit(
+		on_event,
+		ExecEvent::InputDone {
+			index,
+			output: Some(output.clone()),
+		},
+	);
+	Ok(InputOutcome::Ran(output))
+}
+
+/// A monotonically-increasing-enough run id/timestamp pair for `RunRecord`, without pulling
+/// in a dependency on `chrono`/`uuid` for this one call site.
+fn run_id_and_timestamp() -> (String, String) {
+	let since_epoch = std::time::SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.unwrap_or_default();
+	let timestamp = format!("{}.{:09}", since_epoch.as_secs(), since_epoch.subsec_nanos());
+	(format!("run-{timestamp}"), timestamp)
+}
@@ -0,0 +1,182 @@
+//! Durable run-result store backing `devai::last_run` and crash-resume.
+//!
+//! Serializes the full outcome of `run_command_agent` (`outputs`, `after_all`, and a
+//! per-input status) to one JSON file per agent under the devai dir, after every run.
+//! Records carry a `schema_version` so older records can be migrated forward, the same
+//! shape as the existing `migrate_devai_0_1_0_if_needed` flow for the devai dir itself.
+
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum InputStatus {
+	Ran,
+	Skipped,
+	Errored,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+	pub schema_version: u32,
+	pub run_id: String,
+	pub timestamp: String,
+	pub outputs: Vec<Option<Value>>,
+	pub after_all: Option<Value>,
+	pub input_statuses: Vec<InputStatus>,
+}
+
+impl RunRecord {
+	pub fn new(run_id: impl Into<String>, timestamp: impl Into<String>) -> Self {
+		Self {
+			schema_version: CURRENT_SCHEMA_VERSION,
+			run_id: run_id.into(),
+			timestamp: timestamp.into(),
+			outputs: Vec::new(),
+			after_all: None,
+			input_statuses: Vec::new(),
+		}
+	}
+}
+
+/// Migrates an on-disk record (whatever `schema_version` it was saved with) to
+/// `CURRENT_SCHEMA_VERSION`. There is only version 1 today, so this is an identity
+/// migration, but it keeps the same forward-compatible shape as the devai-dir migration.
+fn migrate_record_if_needed(mut value: Value) -> Result<RunRecord> {
+	let version = value.get("schema_version").and_then(Value::as_u64).unwrap_or(0);
+
+	if version == 0 {
+		// Pre-versioning record shape: assume it is already a valid v1 body minus the field.
+		if let Some(obj) = value.as_object_mut() {
+			obj.insert("schema_version".to_string(), Value::from(1));
+		}
+	}
+
+	serde_json::from_value(value).map_err(|err| Error::cc("run result store, failed to migrate record", err))
+}
+
+/// Persists and loads `RunRecord`s, one JSON file per command agent, under the devai dir.
+pub struct RunResultStore {
+	dir: PathBuf,
+}
+
+impl RunResultStore {
+	pub fn new(dir: impl Into<PathBuf>) -> Self {
+		Self { dir: dir.into() }
+	}
+
+	pub fn record_path(&self, agent_key: &str) -> PathBuf {
+		self.dir.join(format!("{agent_key}.json"))
+	}
+
+	pub fn save(&self, agent_key: &str, record: &RunRecord) -> Result<()> {
+		fs::create_dir_all(&self.dir).map_err(|err| Error::cc("run result store, failed to create dir", err))?;
+		let content =
+			serde_json::to_string_pretty(record).map_err(|err| Error::cc("run result store, failed to serialize", err))?;
+		fs::write(self.record_path(agent_key), content).map_err(|err| Error::cc("run result store, failed to write", err))
+	}
+
+	pub fn load_last_run(&self, agent_key: &str) -> Result<Option<RunRecord>> {
+		let path = self.record_path(agent_key);
+		if !path.exists() {
+			return Ok(None);
+		}
+		let content = fs::read_to_string(&path).map_err(|err| Error::cc("run result store, failed to read", err))?;
+		let value: Value =
+			serde_json::from_str(&content).map_err(|err| Error::cc("run result store, failed to parse", err))?;
+		Ok(Some(migrate_record_if_needed(value)?))
+	}
+}
+
+/// For `--resume`: given the prior record and the number of inputs in the current run,
+/// returns the indices that must be re-executed -- those that previously errored, and
+/// those beyond what the prior run ever reached. Indices that previously ran or were
+/// skipped are left alone so their stored output can be fed straight through.
+pub fn inputs_to_rerun(record: &RunRecord, total_inputs: usize) -> Vec<usize> {
+	let mut rerun = Vec::new();
+
+	for i in 0..total_inputs {
+		match record.input_statuses.get(i) {
+			Some(InputStatus::Errored) | None => rerun.push(i),
+			Some(InputStatus::Ran) | Some(InputStatus::Skipped) => {}
+		}
+	}
+
+	rerun
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	type Error = Box<dyn std::error::Error>;
+	type Result<T> = core::result::Result<T, Error>;
+
+	use super::*;
+	use serde_json::json;
+
+	fn tmp_store_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("devai_test_run_result_store_{name}"));
+		let _ = fs::remove_dir_all(&dir);
+		dir
+	}
+
+	#[test]
+	fn test_run_result_store_roundtrip() -> Result<()> {
+		let dir = tmp_store_dir("roundtrip");
+		let store = RunResultStore::new(&dir);
+
+		assert_eq!(store.load_last_run("agent-hello")?.is_none(), true);
+
+		let mut record = RunRecord::new("run-1", "2026-07-30T00:00:00Z");
+		record.outputs = vec![Some(json!("a")), Some(json!("b"))];
+		record.after_all = Some(json!({"count": 2}));
+		record.input_statuses = vec![InputStatus::Ran, InputStatus::Ran];
+
+		store.save("agent-hello", &record)?;
+
+		let loaded = store.load_last_run("agent-hello")?.expect("record should be present");
+		assert_eq!(loaded.run_id, "run-1");
+		assert_eq!(loaded.outputs, record.outputs);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_run_result_store_migrates_unversioned_record() -> Result<()> {
+		let dir = tmp_store_dir("migrate");
+		let store = RunResultStore::new(&dir);
+
+		fs::create_dir_all(&dir)?;
+		let legacy = json!({
+			"run_id": "run-legacy",
+			"timestamp": "2026-01-01T00:00:00Z",
+			"outputs": [],
+			"after_all": null,
+			"input_statuses": []
+		});
+		fs::write(store.record_path("agent-legacy"), legacy.to_string())?;
+
+		let loaded = store.load_last_run("agent-legacy")?.expect("record should be present");
+		assert_eq!(loaded.schema_version, CURRENT_SCHEMA_VERSION);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_inputs_to_rerun_skips_ran_and_skipped_reruns_errored_and_unreached() {
+		let mut record = RunRecord::new("run-1", "2026-07-30T00:00:00Z");
+		record.input_statuses = vec![InputStatus::Ran, InputStatus::Errored, InputStatus::Skipped];
+
+		// total_inputs = 5: index 3 and 4 were never reached by the prior (interrupted) run.
+		let rerun = inputs_to_rerun(&record, 5);
+
+		assert_eq!(rerun, vec![1, 3, 4]);
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,106 @@
+//! Per-run shared state backing `devai::state_set` / `devai::state_get` / `devai::state_all`.
+//!
+//! `RuntimeContext` holds one `RunState`, created fresh in `run_command_agent` for every
+//! top-level call, and shares it (by cloning the `Arc`) with `before_all`, each input's
+//! `# Data`, the AI step, `# Output`, and `# After All`. A nested `devai::run(...)` builds
+//! its own `RuntimeContext` with `RunState::new()` rather than cloning the caller's, so by
+//! default it starts with empty state unless the caller explicitly threads its own state in.
+
+use crate::{Error, Result};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone)]
+pub struct RunState {
+	inner: Arc<Mutex<Value>>,
+}
+
+impl Default for RunState {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl RunState {
+	/// Create a fresh, empty state. Called once per top-level `run_command_agent` — never
+	/// shared implicitly with a nested run.
+	pub fn new() -> Self {
+		Self {
+			inner: Arc::new(Mutex::new(Value::Object(Default::default()))),
+		}
+	}
+
+	pub fn set(&self, key: &str, value: Value) -> Result<()> {
+		let mut guard = self.inner.lock().map_err(|_| Error::cc("RunState poisoned", "state_set"))?;
+		guard
+			.as_object_mut()
+			.ok_or_else(|| Error::cc("RunState, internal value is not an object", "state_set"))?
+			.insert(key.to_string(), value);
+		Ok(())
+	}
+
+	pub fn get(&self, key: &str) -> Result<Option<Value>> {
+		let guard = self.inner.lock().map_err(|_| Error::cc("RunState poisoned", "state_get"))?;
+		Ok(guard.as_object().and_then(|map| map.get(key)).cloned())
+	}
+
+	pub fn all(&self) -> Result<Value> {
+		let guard = self.inner.lock().map_err(|_| Error::cc("RunState poisoned", "state_all"))?;
+		Ok(guard.clone())
+	}
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	type Error = Box<dyn std::error::Error>;
+	type Result<T> = core::result::Result<T, Error>;
+
+	use super::*;
+
+	#[test]
+	fn test_run_state_set_get_roundtrip() -> Result<()> {
+		let state = RunState::new();
+		state.set("count", Value::from(1))?;
+
+		assert_eq!(state.get("count")?, Some(Value::from(1)));
+		assert_eq!(state.get("missing")?, None);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_run_state_all_reflects_every_key() -> Result<()> {
+		let state = RunState::new();
+		state.set("a", Value::from(1))?;
+		state.set("b", Value::from("two"))?;
+
+		let all = state.all()?;
+
+		assert_eq!(all["a"], Value::from(1));
+		assert_eq!(all["b"], Value::from("two"));
+
+		Ok(())
+	}
+
+	/// A nested `devai::run(...)` gets a brand new `RunState`, not the caller's — mutating
+	/// one must never be observed through the other.
+	#[test]
+	fn test_run_state_nested_run_is_isolated_by_default() -> Result<()> {
+		let parent = RunState::new();
+		parent.set("count", Value::from(1))?;
+
+		// This is what a nested `devai::run(...)` constructs for itself.
+		let nested = RunState::new();
+
+		assert_eq!(nested.get("count")?, None);
+
+		nested.set("count", Value::from(99))?;
+		assert_eq!(parent.get("count")?, Some(Value::from(1)));
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,131 @@
+//! Ties together everything a running agent script needs. Built once per
+//! [`crate::run::run_command_agent`] call and cloned (the state, run-result store and
+//! current-cache fields are shared `Arc`s; `dir_context` is copied) into each `devai::*`
+//! Rhai function registered by
+//! `rhai_modules::rhai_devai::rhai_module`.
+
+use crate::run::{AgentCache, CacheDeps, DirContext, Runtime, RunResultStore, RunState};
+use crate::{Error, Result};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone)]
+pub struct RuntimeContext {
+	dir_context: DirContext,
+	runtime: Runtime,
+	state: RunState,
+	run_result_store: Arc<RunResultStore>,
+	current_cache: Arc<Mutex<Option<CurrentCache>>>,
+}
+
+#[derive(Clone)]
+struct CurrentCache {
+	agent_cache: Arc<AgentCache>,
+	deps: CacheDeps,
+	input: Value,
+}
+
+impl RuntimeContext {
+	pub fn new(dir_context: DirContext, runtime: Runtime, state: RunState, run_result_store: RunResultStore) -> Self {
+		Self {
+			dir_context,
+			runtime,
+			state,
+			run_result_store: Arc::new(run_result_store),
+			current_cache: Arc::new(Mutex::new(None)),
+		}
+	}
+
+	pub fn dir_context(&self) -> &DirContext {
+		&self.dir_context
+	}
+
+	pub fn get_runtime(&self) -> Result<Runtime> {
+		Ok(self.runtime.clone())
+	}
+
+	pub fn run_result_store(&self) -> Result<&RunResultStore> {
+		Ok(&self.run_result_store)
+	}
+
+	// region:    --- state
+
+	pub fn state_set(&self, key: &str, value: Value) -> Result<()> {
+		self.state.set(key, value)
+	}
+
+	pub fn state_get(&self, key: &str) -> Result<Option<Value>> {
+		self.state.get(key)
+	}
+
+	pub fn state_all(&self) -> Result<Value> {
+		self.state.all()
+	}
+
+	// endregion: --- state
+
+	// region:    --- cache
+
+	/// Called by `run_command_agent` right before each input's `# Data` block runs, so the
+	/// `devai::depends_on`/`devai::depends_on_env`/`devai::cache_skip_if_fresh` calls made
+	/// while that block is executing operate on that input's own declared dependencies, not
+	/// a previous input's.
+	pub fn set_current_cache(&self, agent_cache: Arc<AgentCache>, deps: CacheDeps, input: Value) -> Result<()> {
+		let mut guard = self
+			.current_cache
+			.lock()
+			.map_err(|_| Error::cc("RuntimeContext poisoned", "set_current_cache"))?;
+		*guard = Some(CurrentCache { agent_cache, deps, input });
+		Ok(())
+	}
+
+	/// Called once the last input has finished, so a stray `devai::depends_on(...)` call
+	/// after the loop (e.g. from `# After All`) has no current input to attach to.
+	pub fn clear_current_cache(&self) -> Result<()> {
+		let mut guard = self
+			.current_cache
+			.lock()
+			.map_err(|_| Error::cc("RuntimeContext poisoned", "clear_current_cache"))?;
+		*guard = None;
+		Ok(())
+	}
+
+	pub fn cache_depends_on(&self, path: &str) -> Result<()> {
+		self.with_current_deps(|deps| deps.depends_on(path))
+	}
+
+	pub fn cache_depends_on_env(&self, name: &str) -> Result<()> {
+		self.with_current_deps(|deps| deps.depends_on_env(name))
+	}
+
+	pub fn cache_is_fresh(&self) -> Result<bool> {
+		Ok(self.cache_fresh_output()?.is_some())
+	}
+
+	/// The stored output for the current input if its cache is fresh, so
+	/// `devai::cache_skip_if_fresh` can hand the actual stored output back instead of just a
+	/// yes/no, letting the `# Data` block reuse it instead of re-calling the model.
+	pub fn cache_fresh_output(&self) -> Result<Option<Value>> {
+		let guard = self
+			.current_cache
+			.lock()
+			.map_err(|_| Error::cc("RuntimeContext poisoned", "cache_fresh_output"))?;
+		let Some(current) = guard.as_ref() else {
+			return Ok(None);
+		};
+		current.agent_cache.fresh_output(&current.input, &current.deps)
+	}
+
+	fn with_current_deps(&self, f: impl FnOnce(&CacheDeps) -> Result<()>) -> Result<()> {
+		let guard = self
+			.current_cache
+			.lock()
+			.map_err(|_| Error::cc("RuntimeContext poisoned", "current cache"))?;
+		let current = guard
+			.as_ref()
+			.ok_or_else(|| Error::cc("devai cache", "no input is currently executing"))?;
+		f(&current.deps)
+	}
+
+	// endregion: --- cache
+}
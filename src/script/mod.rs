@@ -0,0 +1,73 @@
+// region:    --- Modules
+
+pub mod rhai_script;
+
+// endregion: --- Modules
+
+use crate::script::rhai_script::dynamic_helpers::value_to_dynamic;
+use rhai::Dynamic;
+
+/// Small builder/accessor around a Rhai `Map`, used wherever the runtime hands a script an
+/// object literal or needs to read one back (`devai::before_all_response`'s `_devai_`
+/// envelope, `devai::run_with`'s `options`).
+#[derive(Debug, Clone, Default)]
+pub struct DynamicMap(rhai::Map);
+
+impl DynamicMap {
+	pub fn insert(mut self, key: impl Into<String>, value: impl IntoDynamic) -> Self {
+		self.0.insert(key.into().into(), value.into_dynamic());
+		self
+	}
+
+	/// Reads `key` back out, downcast to `T`. Returns `None` if the key is absent or holds a
+	/// value of a different type.
+	pub fn get<T: Clone + 'static>(&self, key: &str) -> Option<T> {
+		self.0.get(key).and_then(|dynamic| dynamic.clone().try_cast::<T>())
+	}
+
+	pub fn from_dynamic(dynamic: Dynamic) -> std::result::Result<Self, &'static str> {
+		dynamic.try_cast::<rhai::Map>().map(Self).ok_or("value is not an object map")
+	}
+}
+
+impl From<DynamicMap> for Dynamic {
+	fn from(map: DynamicMap) -> Self {
+		map.0.into()
+	}
+}
+
+/// Converts a value into a Rhai `Dynamic`; implemented for the types `DynamicMap::insert` is
+/// actually called with, rather than as a catch-all `Into<Dynamic>` replacement.
+pub trait IntoDynamic {
+	fn into_dynamic(self) -> Dynamic;
+}
+
+impl IntoDynamic for Dynamic {
+	fn into_dynamic(self) -> Dynamic {
+		self
+	}
+}
+
+impl IntoDynamic for &str {
+	fn into_dynamic(self) -> Dynamic {
+		self.into()
+	}
+}
+
+impl IntoDynamic for String {
+	fn into_dynamic(self) -> Dynamic {
+		self.into()
+	}
+}
+
+impl IntoDynamic for DynamicMap {
+	fn into_dynamic(self) -> Dynamic {
+		self.into()
+	}
+}
+
+impl IntoDynamic for serde_json::Value {
+	fn into_dynamic(self) -> Dynamic {
+		value_to_dynamic(&self)
+	}
+}
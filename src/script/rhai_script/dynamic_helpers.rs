@@ -0,0 +1,19 @@
+//! Conversions between Rhai's `Dynamic` and `serde_json::Value`, used at every boundary
+//! between agent scripts and the rest of the crate: script inputs, `devai::state_*`, and
+//! cached/persisted outputs.
+
+use crate::{Error, Result};
+use rhai::Dynamic;
+use serde_json::Value;
+
+pub fn value_to_dynamic(value: &Value) -> Dynamic {
+	rhai::serde::to_dynamic(value).unwrap_or(Dynamic::UNIT)
+}
+
+pub fn dynamic_to_value(dynamic: Dynamic) -> Result<Value> {
+	rhai::serde::from_dynamic(&dynamic).map_err(|err| Error::cc("failed to convert Dynamic to a json value", err))
+}
+
+pub fn dynamics_to_values(dynamics: Vec<Dynamic>) -> Result<Vec<Value>> {
+	dynamics.into_iter().map(dynamic_to_value).collect()
+}
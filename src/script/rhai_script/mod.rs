@@ -0,0 +1,11 @@
+// region:    --- Modules
+
+mod rhai_engine;
+mod reserved_names;
+
+pub mod dynamic_helpers;
+pub mod rhai_modules;
+
+pub use rhai_engine::*;
+
+// endregion: --- Modules
@@ -0,0 +1,114 @@
+//! Guards the Rhai engine used for agents against shadowing of runtime-injected names
+//! and against silently duplicated function definitions within a single agent script.
+//!
+//! ---
+//!
+//! Wired into `script::rhai_script::rhai_engine::build_agent_engine` (`Engine::on_def_var`
+//! for the variable guard) and `compile_agent_script` (an AST pass for the function guard),
+//! which `run_command_agent` calls on every real run -- not just in these modules' own unit
+//! tests. Both turn what would otherwise be a silently broken flow (e.g. a `# Data` block
+//! that stopped seeing `input` because a user wrote `let input = ...`) into a hard,
+//! actionable error pointing at the offending source position.
+
+use crate::Error;
+use rhai::{EvalAltResult, Position, AST};
+
+/// Names injected by the runtime into every agent script scope. A `let`/`const` that
+/// shadows one of these is almost always a mistake, so it is rejected outright.
+pub(crate) const RESERVED_VAR_NAMES: &[&str] = &["input", "before_all", "options", "devai"];
+
+/// Install this via `engine.on_def_var(...)` when building the engine that evaluates
+/// agent scripts. Returning `Ok(false)` tells Rhai to reject the definition; the
+/// `EvalAltResult` carried by the `Err` path is what the caller actually sees, so we
+/// return `Err` directly to get our own message and position through.
+pub fn guard_reserved_var(name: &str, position: Position) -> Result<bool, Box<EvalAltResult>> {
+	if RESERVED_VAR_NAMES.contains(&name) {
+		return Err(Error::ReservedVarShadowed {
+			name: name.to_string(),
+			position,
+		}
+		.into());
+	}
+
+	Ok(true)
+}
+
+/// Walk a compiled agent script and reject a second definition of the same named
+/// function. Call this right after `Engine::compile(...)`, before the AST is evaluated.
+pub fn guard_no_duplicate_fn(ast: &AST) -> crate::Result<()> {
+	let mut seen: Vec<&str> = Vec::new();
+
+	for f in ast.iter_functions() {
+		if seen.contains(&f.name) {
+			return Err(Error::DuplicateFunctionDef {
+				name: f.name.to_string(),
+			});
+		}
+		seen.push(f.name);
+	}
+
+	Ok(())
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	type Error = Box<dyn std::error::Error>;
+	type Result<T> = core::result::Result<T, Error>;
+
+	use super::*;
+	use rhai::Engine;
+
+	#[test]
+	fn test_guard_reserved_var_rejects_input() {
+		let res = guard_reserved_var("input", Position::NONE);
+		assert!(res.is_err(), "shadowing `input` must be rejected");
+	}
+
+	#[test]
+	fn test_guard_reserved_var_rejects_every_reserved_name() {
+		for name in RESERVED_VAR_NAMES {
+			let res = guard_reserved_var(name, Position::NONE);
+			assert!(res.is_err(), "shadowing `{name}` must be rejected");
+		}
+	}
+
+	#[test]
+	fn test_guard_reserved_var_allows_other_names() -> Result<()> {
+		let allowed = guard_reserved_var("my_count", Position::NONE)?;
+		assert!(allowed);
+		Ok(())
+	}
+
+	#[test]
+	fn test_guard_no_duplicate_fn_rejects_second_definition() -> Result<()> {
+		let engine = Engine::new();
+		let ast = engine.compile(
+			r#"
+			fn build_prompt() { "first" }
+			fn build_prompt() { "second" }
+			"#,
+		)?;
+
+		let res = guard_no_duplicate_fn(&ast);
+		assert!(res.is_err(), "a second `fn build_prompt()` must be rejected");
+		Ok(())
+	}
+
+	#[test]
+	fn test_guard_no_duplicate_fn_allows_distinct_names() -> Result<()> {
+		let engine = Engine::new();
+		let ast = engine.compile(
+			r#"
+			fn build_prompt() { "first" }
+			fn build_footer() { "second" }
+			"#,
+		)?;
+
+		guard_no_duplicate_fn(&ast)?;
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
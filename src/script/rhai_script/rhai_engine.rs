@@ -0,0 +1,188 @@
+//! Builds the Rhai `Engine` used to evaluate agent scripts.
+//!
+//! ---
+//!
+//! This is where the per-agent-script guards live: a `FileModuleResolver` rooted at the
+//! devai dir's `lib/` directory so `import "utils" as u;` resolves shared library modules
+//! (see `rhai_devai`'s module doc), with cycle detection and a hard error when an import
+//! path would resolve outside that root.
+
+use crate::script::rhai_script::reserved_names::{guard_no_duplicate_fn, guard_reserved_var};
+use crate::{Error, Result};
+use rhai::module_resolvers::FileModuleResolver;
+use rhai::{Engine, EvalAltResult, Module, ModuleResolver, Position, AST};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Builds the engine agent scripts are compiled and run with, rooting its module resolver
+/// at `lib_dir` (the devai dir's `lib/` directory, see `DevaiDir::get_lib_dir`) and
+/// rejecting shadowing of the runtime-injected names (`input`, `before_all`, `options`,
+/// `devai`) via `on_def_var`.
+pub fn build_agent_engine(lib_dir: impl AsRef<Path>) -> Engine {
+	let mut engine = Engine::new();
+
+	engine.set_module_resolver(RootedFileModuleResolver::new(lib_dir.as_ref()));
+
+	engine.on_def_var(|_is_new_def, info, _context| guard_reserved_var(info.name, info.position));
+
+	engine
+}
+
+/// Compiles `script` with `engine`, then rejects a script that defines the same named
+/// function twice (an accidentally pasted second `fn build_prompt()`). Call this instead
+/// of `engine.compile(...)` directly when compiling an agent script.
+pub fn compile_agent_script(engine: &Engine, script: &str) -> Result<AST> {
+	let ast = engine
+		.compile(script)
+		.map_err(|err| Error::cc("devai agent script, failed to compile", err))?;
+
+	guard_no_duplicate_fn(&ast)?;
+
+	Ok(ast)
+}
+
+/// Wraps Rhai's stock `FileModuleResolver`, additionally rejecting:
+/// - an import path that canonicalizes outside `root` ("escapes the devai root")
+/// - a module that is still being resolved higher up the current import stack (a cycle)
+struct RootedFileModuleResolver {
+	root: PathBuf,
+	inner: FileModuleResolver,
+	resolving: RefCell<HashSet<PathBuf>>,
+}
+
+impl RootedFileModuleResolver {
+	fn new(root: &Path) -> Self {
+		Self {
+			root: root.to_path_buf(),
+			inner: FileModuleResolver::new_with_path(root),
+			resolving: RefCell::new(HashSet::new()),
+		}
+	}
+
+	fn resolve_within_root(&self, path: &str) -> std::result::Result<PathBuf, Error> {
+		// The inner `FileModuleResolver` always loads `<path>.rhai` (it replaces any extension
+		// the import path has), so check that same file.
+		let mut candidate = self.root.join(path);
+		candidate.set_extension("rhai");
+		let canonical = candidate
+			.canonicalize()
+			.map_err(|err| Error::cc("devai import, could not resolve module path", err))?;
+
+		if !canonical.starts_with(&self.root) {
+			return Err(Error::cc(
+				"devai import, module path escapes the devai lib dir",
+				path.to_string(),
+			));
+		}
+
+		Ok(canonical)
+	}
+}
+
+impl ModuleResolver for RootedFileModuleResolver {
+	fn resolve(
+		&self,
+		engine: &Engine,
+		source: Option<&str>,
+		path: &str,
+		pos: Position,
+	) -> std::result::Result<Arc<Module>, Box<EvalAltResult>> {
+		let canonical = self
+			.resolve_within_root(path)
+			.map_err(|err| Box::new(EvalAltResult::ErrorInModule(path.to_string(), err.into(), pos)))?;
+
+		if !self.resolving.borrow_mut().insert(canonical.clone()) {
+			let err = Error::cc("devai import, cyclic import detected", path.to_string());
+			return Err(Box::new(EvalAltResult::ErrorInModule(path.to_string(), err.into(), pos)));
+		}
+
+		let result = self.inner.resolve(engine, source, path, pos);
+
+		self.resolving.borrow_mut().remove(&canonical);
+
+		result
+	}
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	type Error = Box<dyn std::error::Error>;
+	type Result<T> = core::result::Result<T, Error>;
+
+	use super::*;
+	use std::fs::{create_dir_all, write};
+
+	#[test]
+	fn test_rhai_engine_resolve_within_root_ok() -> Result<()> {
+		let root = std::env::temp_dir().join("devai_test_rhai_engine_ok");
+		create_dir_all(&root)?;
+		write(root.join("utils.rhai"), "fn noop() {}")?;
+
+		let resolver = RootedFileModuleResolver::new(&root);
+		let resolved = resolver.resolve_within_root("utils.rhai")?;
+
+		assert!(resolved.starts_with(&root));
+		Ok(())
+	}
+
+	#[test]
+	fn test_rhai_engine_rejects_shadowing_input() -> Result<()> {
+		let lib_dir = std::env::temp_dir().join("devai_test_rhai_engine_reserved_var");
+		create_dir_all(&lib_dir)?;
+
+		let engine = build_agent_engine(&lib_dir);
+		let res = engine.eval::<i64>("let input = 1; input");
+
+		assert!(res.is_err(), "`let input = ...` must be rejected");
+		Ok(())
+	}
+
+	#[test]
+	fn test_rhai_engine_allows_ordinary_let() -> Result<()> {
+		let lib_dir = std::env::temp_dir().join("devai_test_rhai_engine_ordinary_var");
+		create_dir_all(&lib_dir)?;
+
+		let engine = build_agent_engine(&lib_dir);
+		let res: i64 = engine.eval("let count = 1; count + 1")?;
+
+		assert_eq!(res, 2);
+		Ok(())
+	}
+
+	#[test]
+	fn test_compile_agent_script_rejects_duplicate_fn() -> Result<()> {
+		let lib_dir = std::env::temp_dir().join("devai_test_rhai_engine_dup_fn");
+		create_dir_all(&lib_dir)?;
+
+		let engine = build_agent_engine(&lib_dir);
+		let res = compile_agent_script(
+			&engine,
+			r#"
+			fn build_prompt() { "first" }
+			fn build_prompt() { "second" }
+			"#,
+		);
+
+		assert!(res.is_err(), "a second `fn build_prompt()` must be rejected");
+		Ok(())
+	}
+
+	#[test]
+	fn test_rhai_engine_resolve_escapes_root_errors() -> Result<()> {
+		let root = std::env::temp_dir().join("devai_test_rhai_engine_escape/lib");
+		create_dir_all(&root)?;
+		write(root.parent().unwrap().join("outside.rhai"), "fn noop() {}")?;
+
+		let resolver = RootedFileModuleResolver::new(&root);
+		let res = resolver.resolve_within_root("../outside.rhai");
+
+		assert!(res.is_err(), "importing a path outside lib_dir must error");
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
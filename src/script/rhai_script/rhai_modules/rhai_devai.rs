@@ -12,17 +12,34 @@
 //! * `devai::before_all_response(data: {inputs?: [], before_all?: any}) -> BeforeAllResponseDict`
 //! * `devai::run(cmd_agent: &str) -> {outputs: null | any[], after_all: null | any}`
 //! * `devai::run(cmd_agent: &str, inputs: Vec<Dynamic>) ->  {outputs: null | any[], after_all: null | any}`
+//! * `devai::run_with(cmd_agent: &str, inputs: Vec<Dynamic>, options: #{ on_event: FnPtr }) -> {outputs: null | any[], after_all: null | any}`
+//! * `devai::last_run(cmd_agent: &str) -> {outputs: null | any[], after_all: null | any}`
+//! * `devai::state_set(key: &str, value: Dynamic)`
+//! * `devai::state_get(key: &str) -> Dynamic`
+//! * `devai::state_all() -> Map`
+//! * `devai::depends_on(path: &str)`
+//! * `devai::depends_on_env(name: &str)`
+//! * `devai::cache_skip_if_fresh() -> SkipDict`
 //!
 //! Note: the SkipDict and BeforeAllResponseDict are not really important, as it is for the internals to treat those return values appropriately.
+//!
+//! ### Shared modules
+//! Agent scripts can `import "some-lib" as lib;` to pull in helper functions from a
+//! `.rhai` file under the devai dir's `lib/` directory (see `DevaiDir::get_lib_dir`). The
+//! import path's extension is always replaced by `.rhai`, so `.devai` files cannot be imported.
+//! `rhai_engine::build_agent_engine` configures the engine that evaluates agent scripts
+//! with a resolver rooted there, so import paths are always relative to `lib/`, can't
+//! escape the devai dir, and a cyclic import is a hard error rather than a stack overflow.
 
 use crate::agent::find_agent;
+use crate::exec::ExecEvent;
 use crate::run::{run_command_agent, RuntimeContext};
 use crate::run::{PathResolver, RunBaseOptions};
-use crate::script::rhai_script::dynamic_helpers::{dynamics_to_values, value_to_dynamic};
+use crate::script::rhai_script::dynamic_helpers::{dynamic_to_value, dynamics_to_values, value_to_dynamic};
 use crate::script::{DynamicMap, IntoDynamic};
 use crate::Error;
 use rhai::plugin::RhaiResult;
-use rhai::{Dynamic, FuncRegistration, Module};
+use rhai::{Dynamic, FnPtr, FuncRegistration, Module, NativeCallContext};
 use serde_json::json;
 
 pub fn rhai_module(runtime_context: &RuntimeContext) -> Module {
@@ -55,11 +72,61 @@ pub fn rhai_module(runtime_context: &RuntimeContext) -> Module {
 			run_with_inputs(&ctx, cmd_agent, Some(inputs))
 		});
 
+	let ctx = runtime_context.clone();
+	FuncRegistration::new("state_set")
+		.in_global_namespace()
+		.set_into_module(&mut module, move |key: &str, value: Dynamic| state_set(&ctx, key, value));
+
+	let ctx = runtime_context.clone();
+	FuncRegistration::new("state_get")
+		.in_global_namespace()
+		.set_into_module(&mut module, move |key: &str| state_get(&ctx, key));
+
+	let ctx = runtime_context.clone();
+	FuncRegistration::new("state_all")
+		.in_global_namespace()
+		.set_into_module(&mut module, move || state_all(&ctx));
+
+	let ctx = runtime_context.clone();
+	FuncRegistration::new("run_with")
+		.in_global_namespace()
+		.set_into_module(
+			&mut module,
+			move |native_ctx: NativeCallContext, cmd_agent: &str, inputs: Vec<Dynamic>, options: Dynamic| {
+				run_with_inputs_and_events(native_ctx, &ctx, cmd_agent, inputs, options)
+			},
+		);
+
+	let ctx = runtime_context.clone();
+	FuncRegistration::new("last_run")
+		.in_global_namespace()
+		.set_into_module(&mut module, move |cmd_agent: &str| last_run(&ctx, cmd_agent));
+
+	let ctx = runtime_context.clone();
+	FuncRegistration::new("depends_on")
+		.in_global_namespace()
+		.set_into_module(&mut module, move |path: &str| depends_on(&ctx, path));
+
+	let ctx = runtime_context.clone();
+	FuncRegistration::new("depends_on_env")
+		.in_global_namespace()
+		.set_into_module(&mut module, move |name: &str| depends_on_env(&ctx, name));
+
+	let ctx = runtime_context.clone();
+	FuncRegistration::new("cache_skip_if_fresh")
+		.in_global_namespace()
+		.set_into_module(&mut module, move || cache_skip_if_fresh(&ctx));
+
 	module
 }
 
 // region:    --- run...
 
+/// The event callback `run_command_agent` always takes; `devai::run` passes this no-op so
+/// that it and `devai::run_with` (which passes a real callback) share one
+/// `run_command_agent` signature instead of the function being overloaded on arity.
+fn noop_on_event(_event: ExecEvent) {}
+
 /// ## RHAI Documentation
 /// ```rhai
 /// run(cmd_agent: &str) -> {outputs: null | any[], after_all: null | any}
@@ -84,7 +151,9 @@ fn run_with_inputs(ctx: &RuntimeContext, cmd_agent: &str, inputs: Option<Vec<Dyn
 	// Note: Require to have
 	let runtime = ctx.get_runtime()?;
 	let res = tokio::task::block_in_place(|| {
-		rt.block_on(async { run_command_agent(&runtime, &agent, inputs, &RunBaseOptions::default(), true).await })
+		rt.block_on(async {
+			run_command_agent(&runtime, &agent, inputs, &RunBaseOptions::default(), true, &noop_on_event).await
+		})
 	})?;
 
 	let res =
@@ -95,8 +164,259 @@ fn run_with_inputs(ctx: &RuntimeContext, cmd_agent: &str, inputs: Option<Vec<Dyn
 	Ok(rhai_res)
 }
 
+/// ## RHAI Documentation
+/// ```rhai
+/// last_run(cmd_agent: &str) -> {outputs: null | any[], after_all: null | any}
+/// ```
+///
+/// Loads the persisted result of the most recent `run_command_agent` invocation of
+/// `cmd_agent` from the run-result store (see `crate::run::RunResultStore`, which
+/// `run_command_agent` writes to after every run), without re-running the agent. Returns
+/// `()` if no run has been persisted for that agent yet.
+///
+/// Note: re-running only the inputs that errored or were never reached is done by
+/// `run_command_agent` when `RunBaseOptions::resume` is set (see `crate::run::inputs_to_rerun`);
+/// `devai::run`/`devai::run_with` do not set it.
+///
+/// for example, in # Data rhai code block:
+///
+/// ```rhai
+/// let prior = devai::last_run("./agent-script/agent-hello.devai");
+/// ```
+fn last_run(ctx: &RuntimeContext, cmd_agent: &str) -> RhaiResult {
+	let agent = find_agent(cmd_agent, ctx.dir_context(), PathResolver::DevaiParentDir)?;
+
+	let record = ctx.run_result_store()?.load_last_run(&agent.key())?;
+
+	let res = match record {
+		Some(record) => {
+			let res = serde_json::to_value(record)
+				.map_err(|err| Error::cc("devai::last_run, failed to result convert to json", err))?;
+			value_to_dynamic(&res)
+		}
+		None => Dynamic::UNIT,
+	};
+
+	Ok(res)
+}
+
+/// ## RHAI Documentation
+/// ```rhai
+/// run_with(cmd_agent: &str, inputs: Vec<Dynamic>, options: #{ on_event: FnPtr }) -> {outputs: null | any[], after_all: null | any}
+/// ```
+///
+/// Like `devai::run`, but `options.on_event` is called with a structured event map
+/// (`{kind: "InputStarted" | "InputSkipped" | "InputDone" | "AfterAllDone" | "Error", data: ...}`)
+/// as the nested run progresses, instead of only returning the final result.
+///
+/// Because the nested run itself uses `block_in_place` + `block_on` under the hood, each
+/// event is dispatched on this same scripting thread in between awaited steps, never
+/// concurrently with the script, so `on_event` can freely touch `devai::state_*`.
+///
+/// for example, in # Data rhai code block:
+///
+/// ```rhai
+/// let result = devai::run_with("./agent-script/agent-hello.devai", ["one", "two"], #{
+///     on_event: |evt| {
+///         print(`event: ${evt.kind}`);
+///     }
+/// });
+/// ```
+fn run_with_inputs_and_events(
+	native_ctx: NativeCallContext,
+	ctx: &RuntimeContext,
+	cmd_agent: &str,
+	inputs: Vec<Dynamic>,
+	options: Dynamic,
+) -> RhaiResult {
+	let options = DynamicMap::from_dynamic(options)
+		.map_err(|err| Error::cc("devai::run_with, options must be an object map", err))?;
+	let on_event: FnPtr = options
+		.get("on_event")
+		.ok_or_else(|| Error::cc("devai::run_with, options.on_event is required", "missing on_event"))?;
+
+	let inputs = dynamics_to_values(inputs)?;
+	let agent = find_agent(cmd_agent, ctx.dir_context(), PathResolver::DevaiParentDir)?;
+
+	let rt = tokio::runtime::Handle::try_current().map_err(Error::TokioTryCurrent)?;
+
+	let runtime = ctx.get_runtime()?;
+	let on_exec_event = |event: ExecEvent| {
+		let event = serde_json::to_value(event).unwrap_or(json!({"kind": "Error", "data": "event serialization failed"}));
+		let _ = on_event.call_within_context::<Dynamic>(&native_ctx, (value_to_dynamic(&event),));
+	};
+	let res = tokio::task::block_in_place(|| {
+		rt.block_on(async {
+			run_command_agent(
+				&runtime,
+				&agent,
+				Some(inputs),
+				&RunBaseOptions::default(),
+				true,
+				&on_exec_event,
+			)
+			.await
+		})
+	})?;
+
+	let res =
+		serde_json::to_value(res).map_err(|err| Error::cc("devai::run_with, failed to result convert to json", err))?;
+
+	Ok(value_to_dynamic(&res))
+}
+
 // endregion: --- run...
 
+// region:    --- state
+
+/// ## RHAI Documentation
+/// ```rhai
+/// state_set(key: &str, value: Dynamic)
+/// ```
+///
+/// Stores `value` under `key` in the per-run state. The state is created once per
+/// `run_command_agent` invocation and is shared by `before_all`, every input's `# Data`,
+/// the AI step, `# Output`, and `# After All`. A nested `devai::run(...)` does NOT see
+/// the caller's state; it starts with its own fresh state.
+///
+/// for example, in # Before All rhai code block:
+///
+/// ```rhai
+/// devai::state_set("count", 0);
+/// ```
+fn state_set(ctx: &RuntimeContext, key: &str, value: Dynamic) -> RhaiResult {
+	let value = dynamic_to_value(value)?;
+	ctx.state_set(key, value)?;
+	Ok(Dynamic::UNIT)
+}
+
+/// ## RHAI Documentation
+/// ```rhai
+/// state_get(key: &str) -> Dynamic
+/// ```
+///
+/// Returns the value previously stored under `key` via `devai::state_set`, or `()` if
+/// nothing has been stored for that key yet.
+///
+/// for example, in # Data rhai code block:
+///
+/// ```rhai
+/// let count = devai::state_get("count");
+/// ```
+fn state_get(ctx: &RuntimeContext, key: &str) -> RhaiResult {
+	let value = ctx.state_get(key)?;
+	let res = match value {
+		Some(value) => value_to_dynamic(&value),
+		None => Dynamic::UNIT,
+	};
+	Ok(res)
+}
+
+/// ## RHAI Documentation
+/// ```rhai
+/// state_all() -> Map
+/// ```
+///
+/// Returns the whole per-run state as a Rhai map.
+///
+/// for example, in # After All rhai code block:
+///
+/// ```rhai
+/// let all = devai::state_all();
+/// ```
+fn state_all(ctx: &RuntimeContext) -> RhaiResult {
+	let value = ctx.state_all()?;
+	Ok(value_to_dynamic(&value))
+}
+
+// endregion: --- state
+
+// region:    --- cache
+
+/// ## RHAI Documentation
+/// ```rhai
+/// depends_on(path: &str)
+/// ```
+///
+/// Registers `path` as a dependency of the current input's `CacheDeps` (see
+/// `crate::run::cache_index`). The input's cache fingerprint is computed from the size and
+/// modification time of every declared dependency path (and env value), so when none of them changed since
+/// the last run, `devai::cache_skip_if_fresh()` can skip re-calling the model. A declared
+/// path that does not exist on disk always invalidates the cache (it is never silently
+/// treated as "unchanged").
+///
+/// for example, in # Data rhai code block:
+///
+/// ```rhai
+/// devai::depends_on("./src/main.rs");
+/// ```
+fn depends_on(ctx: &RuntimeContext, path: &str) -> RhaiResult {
+	ctx.cache_depends_on(path)?;
+	Ok(Dynamic::UNIT)
+}
+
+/// ## RHAI Documentation
+/// ```rhai
+/// depends_on_env(name: &str)
+/// ```
+///
+/// Registers the environment variable `name` as a dependency of the current input, so a
+/// changed value invalidates the cached output just like a changed file would.
+///
+/// for example, in # Data rhai code block:
+///
+/// ```rhai
+/// devai::depends_on_env("OPENAI_API_KEY");
+/// ```
+fn depends_on_env(ctx: &RuntimeContext, name: &str) -> RhaiResult {
+	ctx.cache_depends_on_env(name)?;
+	Ok(Dynamic::UNIT)
+}
+
+/// ## RHAI Documentation
+/// ```rhai
+/// cache_skip_if_fresh() -> SkipDict
+/// ```
+///
+/// Delegates to `RuntimeContext::cache_fresh_output` (see `crate::run::cache_index`), which
+/// computes the fingerprint of the current input (its JSON, every path/env registered via
+/// `devai::depends_on`/`devai::depends_on_env`, and the agent file's own hash) and, if it
+/// matches the fingerprint stored from a previous run, returns the stored output. When
+/// there is a hit, this returns the same `_devai_` `Skip` structure as `devai::skip()`, with
+/// the stored output attached as `data.output` so the `# Data` block can reuse it instead of
+/// calling the model again. Returns `()` when the input is not (yet) cached or is stale
+/// (including when a declared dependency is missing), so the `# Data` block should continue
+/// normally in that case.
+///
+/// for example, in # Data rhai code block:
+///
+/// ```rhai
+/// devai::depends_on("./src/main.rs");
+/// let skip = devai::cache_skip_if_fresh();
+/// if skip != () {
+///   return skip;
+/// }
+/// ```
+fn cache_skip_if_fresh(ctx: &RuntimeContext) -> RhaiResult {
+	let Some(output) = ctx.cache_fresh_output()? else {
+		return Ok(Dynamic::UNIT);
+	};
+
+	let res = json!({
+		"_devai_": {
+			"kind": "Skip",
+			"data": {
+				"reason": "cache fresh, reusing stored output (no declared dependency changed)",
+				"output": output
+			}
+		}
+	});
+
+	Ok(value_to_dynamic(&res))
+}
+
+// endregion: --- cache
+
 // region:    --- before_all_response
 
 /// ## RHAI Documentation
@@ -241,6 +561,24 @@ mod tests {
 		);
 		Ok(())
 	}
+
+	#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+	async fn test_rhai_devai_state_set_get() -> Result<()> {
+		let res = run_reflective_agent(
+			r#"
+			devai::state_set("count", 1);
+			devai::state_set("count", devai::state_get("count") + 1);
+			return devai::state_get("count");
+			"#,
+			None,
+		)
+		.await?;
+
+		let count: i64 = from_value(res)?;
+
+		assert_eq!(count, 2);
+		Ok(())
+	}
 }
 
 // endregion: --- Tests